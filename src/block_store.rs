@@ -0,0 +1,239 @@
+//! An out-of-core `BitStore` backend: the underlying words are split
+//! into fixed-size blocks, each compressed independently, so the data
+//! can be far larger than RAM at the cost of a decompression per
+//! cache miss.
+
+use std::cell::RefCell;
+
+use storage::BitStore;
+
+/// Compresses and decompresses one block's raw bytes.
+///
+/// `BlockStore` is generic over this so callers can trade compression
+/// ratio for (de)compression speed.
+pub trait BlockCodec {
+    /// Compresses `raw`.
+    fn compress(&self, raw: &[u8]) -> Vec<u8>;
+
+    /// Decompresses `compressed` back into `raw_len` bytes.
+    fn decompress(&self, compressed: &[u8], raw_len: usize) -> Vec<u8>;
+}
+
+/// A codec that stores blocks as-is, for callers who want the
+/// `BlockStore` machinery (chunking, out-of-core access, caching)
+/// without paying any compression cost.
+pub struct IdentityCodec;
+
+impl BlockCodec for IdentityCodec {
+    fn compress(&self, raw: &[u8]) -> Vec<u8> {
+        raw.to_vec()
+    }
+
+    fn decompress(&self, compressed: &[u8], raw_len: usize) -> Vec<u8> {
+        debug_assert_eq!(compressed.len(), raw_len);
+        compressed.to_vec()
+    }
+}
+
+#[cfg(feature = "zstd")]
+mod zstd_codec {
+    use super::BlockCodec;
+
+    /// Compresses blocks with zstd at the given level.
+    pub struct ZstdCodec {
+        level: i32,
+    }
+
+    impl ZstdCodec {
+        /// Creates a codec that compresses at `level` (1-22; higher is
+        /// slower but smaller).
+        pub fn new(level: i32) -> Self {
+            ZstdCodec { level: level }
+        }
+    }
+
+    impl BlockCodec for ZstdCodec {
+        fn compress(&self, raw: &[u8]) -> Vec<u8> {
+            ::zstd::encode_all(raw, self.level)
+                .expect("zstd compression cannot fail on an in-memory buffer")
+        }
+
+        fn decompress(&self, compressed: &[u8], raw_len: usize) -> Vec<u8> {
+            let mut out = ::zstd::decode_all(compressed)
+                .expect("decoding a block written by `compress`");
+            out.truncate(raw_len);
+            out
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+pub use self::zstd_codec::ZstdCodec;
+
+fn words_to_bytes(words: &[u64]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * 8);
+    for &word in words {
+        for b in 0..8 {
+            bytes.push((word >> (8 * b)) as u8);
+        }
+    }
+    bytes
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Vec<u64> {
+    let mut words = Vec::with_capacity((bytes.len() + 7) / 8);
+    for chunk in bytes.chunks(8) {
+        let mut word = 0u64;
+        for (b, &byte) in chunk.iter().enumerate() {
+            word |= (byte as u64) << (8 * b);
+        }
+        words.push(word);
+    }
+    words
+}
+
+/// A `BitStore` backed by independently-compressed, fixed-size blocks
+/// of 64-bit words, with a single-block cache for the most recently
+/// decoded block.
+///
+/// `get_block`/`get_bit` locate the target block from the byte-offset
+/// index built in `new`, decompress it into the cache if it isn't
+/// already there, and serve the query from the cached plaintext. This
+/// lets rank/select supports and `CompressedVec` operate on data far
+/// larger than RAM, at the cost of per-block decode latency on a cache
+/// miss.
+pub struct BlockStore<Codec: BlockCodec> {
+    codec: Codec,
+    words_per_block: usize,
+    word_len: usize,
+    bit_len: u64,
+    compressed: Vec<u8>,
+    /// `offsets[i]..offsets[i + 1]` is block `i`'s byte range within
+    /// `compressed`.
+    offsets: Vec<usize>,
+    cache: RefCell<Option<(usize, Vec<u64>)>>,
+}
+
+impl<Codec: BlockCodec> BlockStore<Codec> {
+    /// Compresses `words` into blocks of `words_per_block` words each
+    /// (the last block may be shorter) using `codec`.
+    pub fn new(words: &[u64], bit_len: u64, words_per_block: usize, codec: Codec) -> Self {
+        assert!(words_per_block > 0);
+
+        let mut compressed = Vec::new();
+        let mut offsets = vec![0];
+
+        for chunk in words.chunks(words_per_block) {
+            compressed.extend(codec.compress(&words_to_bytes(chunk)));
+            offsets.push(compressed.len());
+        }
+
+        BlockStore {
+            codec: codec,
+            words_per_block: words_per_block,
+            word_len: words.len(),
+            bit_len: bit_len,
+            compressed: compressed,
+            offsets: offsets,
+            cache: RefCell::new(None),
+        }
+    }
+
+    /// Returns word `offset` of `block`, decompressing into the cache
+    /// first on a miss. Unlike returning the whole decompressed block,
+    /// this never clones it: a cache hit indexes straight into the
+    /// `Ref`, and a miss moves the freshly decompressed block into the
+    /// cache instead of cloning it back out.
+    fn word_in_block(&self, block: usize, offset: usize) -> u64 {
+        {
+            let cache = self.cache.borrow();
+            if let Some((cached_block, ref words)) = *cache {
+                if cached_block == block {
+                    return words[offset];
+                }
+            }
+        }
+
+        let start = self.offsets[block];
+        let end = self.offsets[block + 1];
+        let block_count = self.offsets.len() - 1;
+        let words_in_block = if block + 1 < block_count {
+            self.words_per_block
+        } else {
+            self.word_len - block * self.words_per_block
+        };
+
+        let raw = self.codec.decompress(&self.compressed[start..end], words_in_block * 8);
+        let words = bytes_to_words(&raw);
+        let value = words[offset];
+
+        *self.cache.borrow_mut() = Some((block, words));
+        value
+    }
+}
+
+impl<Codec: BlockCodec> BitStore for BlockStore<Codec> {
+    type Block = u64;
+
+    fn block_len(&self) -> usize {
+        self.word_len
+    }
+
+    fn bit_len(&self) -> u64 {
+        self.bit_len
+    }
+
+    fn get_block(&self, index: usize) -> u64 {
+        let block = index / self.words_per_block;
+        let offset = index % self.words_per_block;
+        self.word_in_block(block, offset)
+    }
+
+    fn get_bit(&self, index: u64) -> bool {
+        // Bits are numbered MSB-first within a word, matching the
+        // `[u64]` `BitStore` impl and the rest of the crate.
+        let word = self.get_block((index / 64) as usize);
+        (word >> (63 - index % 64)) & 1 == 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn get_block_and_get_bit_match_input() {
+        let words: Vec<u64> = (0..37u64).map(|i| i.wrapping_mul(0x9e3779b97f4a7c15).wrapping_add(i)).collect();
+        let bit_len = words.len() as u64 * 64;
+        let store = BlockStore::new(&words, bit_len, 5, IdentityCodec);
+
+        assert_eq!(store.block_len(), words.len());
+        assert_eq!(store.bit_len(), bit_len);
+
+        for i in 0..words.len() {
+            assert_eq!(store.get_block(i), words[i]);
+        }
+
+        for i in 0..bit_len {
+            // MSB-first: bit 0 of a word is its top bit.
+            let expected = (words[(i / 64) as usize] >> (63 - i % 64)) & 1 == 1;
+            assert_eq!(store.get_bit(i), expected);
+        }
+    }
+
+    #[test]
+    fn single_word_block_is_its_own_block() {
+        let words: Vec<u64> = vec![0xdead_beef_cafe_f00d];
+        let store = BlockStore::new(&words, 64, 1, IdentityCodec);
+
+        assert_eq!(store.get_block(0), words[0]);
+    }
+
+    #[test]
+    fn get_bit_is_msb_first() {
+        let store = BlockStore::new(&[0x8000_0000_0000_0000u64], 64, 1, IdentityCodec);
+
+        assert!(store.get_bit(0));
+        assert!(!store.get_bit(1));
+    }
+}
@@ -0,0 +1,123 @@
+//! Broadword (SWAR) bit-manipulation primitives.
+//!
+//! These are the low-level building blocks shared by the rank/select
+//! support structures: counting and locating set bits within a single
+//! machine word without looping bit-by-bit over the whole word.
+
+/// Minimal operations needed on the fixed-size words used as the
+/// storage unit for block-based rank/select structures.
+pub trait BlockType: Copy {
+    /// Number of one bits in `self`.
+    fn count_ones(self) -> u32;
+
+    /// Widens `self` to a `u64`, zero-extending.
+    fn to_u64(self) -> u64;
+}
+
+macro_rules! impl_block_type {
+    ($($ty:ty),*) => {
+        $(
+            impl BlockType for $ty {
+                fn count_ones(self) -> u32 {
+                    <$ty>::count_ones(self)
+                }
+
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+            }
+        )*
+    }
+}
+
+impl_block_type!(u8, u16, u32, u64);
+
+/// Returns the position of the `rank`th (0-indexed) set bit in `word`,
+/// or a value `>= 64` if `word` has `rank` or fewer one bits.
+///
+/// This is the broadword select algorithm: compute the population
+/// count of every byte lane in parallel, fold them into an inclusive
+/// prefix sum per lane with a single multiply, then compare that
+/// against `rank` (broadcast into every lane) to find the byte holding
+/// the target bit in one step. The final bit inside that byte is found
+/// by scanning that byte's bits one at a time.
+pub fn select_in_word(word: u64, rank: u32) -> u32 {
+    debug_assert!(rank < 64);
+
+    // Per-byte population counts, folded into an inclusive prefix sum
+    // across the eight byte lanes by the final multiply.
+    let mut v = word - ((word >> 1) & 0x5555_5555_5555_5555);
+    v = (v & 0x3333_3333_3333_3333) + ((v >> 2) & 0x3333_3333_3333_3333);
+    v = (v + (v >> 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    let byte_prefix_sums = v.wrapping_mul(0x0101_0101_0101_0101);
+
+    // Compare `rank + 1` against every lane at once to find the first
+    // byte whose prefix sum covers the target bit.
+    let target = (rank as u64 + 1).wrapping_mul(0x0101_0101_0101_0101);
+    let ge = (byte_prefix_sums | 0x8080_8080_8080_8080).wrapping_sub(target) & 0x8080_8080_8080_8080;
+    if ge == 0 {
+        // No lane reaches `rank + 1`: the word has too few one bits.
+        return 64;
+    }
+    let byte_nr = (ge.trailing_zeros() / 8) * 8;
+
+    let rank_before_byte = if byte_nr == 0 {
+        0
+    } else {
+        (byte_prefix_sums >> (byte_nr - 8)) & 0xff
+    };
+
+    let mut remaining = rank as u64 - rank_before_byte;
+    let byte = (word >> byte_nr) & 0xff;
+
+    let mut bit = 0;
+    let mut b = byte;
+    loop {
+        if b & 1 == 1 {
+            if remaining == 0 {
+                break;
+            }
+            remaining -= 1;
+        }
+        b >>= 1;
+        bit += 1;
+    }
+
+    byte_nr + bit
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn naive_select(word: u64, rank: u32) -> u32 {
+        let mut remaining = rank;
+        for i in 0..64 {
+            if (word >> i) & 1 == 1 {
+                if remaining == 0 {
+                    return i;
+                }
+                remaining -= 1;
+            }
+        }
+        panic!("not enough bits in word")
+    }
+
+    #[test]
+    fn select_in_word_matches_naive_scan() {
+        let words = [
+            0u64,
+            1,
+            !0u64,
+            0xaaaa_aaaa_aaaa_aaaa,
+            0x8000_0000_0000_0001,
+            0x0102_0304_0506_0708,
+        ];
+
+        for &word in &words {
+            for rank in 0..word.count_ones() {
+                assert_eq!(select_in_word(word, rank), naive_select(word, rank));
+            }
+        }
+    }
+}
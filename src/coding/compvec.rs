@@ -0,0 +1,197 @@
+//! Indexed containers built on top of the universal codes: a sequence
+//! of `u64`s packed back-to-back, with sampled random access.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use super::traits::UniversalCode;
+
+/// Starting size, in bits, of the working window `get` decodes a
+/// single codeword out of before doubling and retrying. Generous
+/// enough for one `elias` or `fib` codeword of a `u64` on the first
+/// attempt; a `unary` codeword grows with its value, so only small
+/// values fit on the first attempt.
+const INITIAL_BITS_PER_CODEWORD: usize = 128;
+
+/// A sequence of `u64`s encoded back-to-back with `Code`, plus a
+/// sampled index of bit offsets so `get` doesn't have to decode from
+/// the start of the stream.
+///
+/// Every `sample_stride`th element's bit offset is recorded; `get(i)`
+/// seeks to the nearest sample at or before `i` and decodes forward
+/// from there. A smaller stride trades space in the sample index for
+/// faster access.
+pub struct CompressedVec<Code: UniversalCode> {
+    bits: VecDeque<bool>,
+    len: usize,
+    sample_stride: usize,
+    /// `samples[k]` is the bit offset where the code for element
+    /// `k * sample_stride` begins.
+    samples: Vec<usize>,
+    _code: PhantomData<Code>,
+}
+
+impl<Code: UniversalCode> CompressedVec<Code> {
+    /// Encodes `values` with `Code`, sampling a bit offset every
+    /// `sample_stride` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` contains `u64::max_value()`: universal codes
+    /// represent positive integers, so elements are shifted up by one
+    /// before encoding, and that value has no representation.
+    pub fn new(values: &[u64], sample_stride: usize) -> Self {
+        assert!(sample_stride > 0);
+
+        let mut bits = VecDeque::new();
+        let mut samples = Vec::with_capacity(values.len() / sample_stride + 1);
+
+        for (i, &value) in values.iter().enumerate() {
+            if i % sample_stride == 0 {
+                samples.push(bits.len());
+            }
+            assert!(value != u64::max_value(), "CompressedVec cannot represent u64::max_value()");
+            // Universal codes represent positive integers, so values
+            // are shifted up by one and back down on the way out.
+            Code::encode(&mut bits, value + 1).expect("encoding to a bit buffer cannot fail");
+        }
+
+        CompressedVec {
+            bits: bits,
+            len: values.len(),
+            sample_stride: sample_stride,
+            samples: samples,
+            _code: PhantomData,
+        }
+    }
+
+    /// Number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decodes and returns the element at `index`.
+    pub fn get(&self, index: usize) -> u64 {
+        assert!(index < self.len);
+
+        let sample = index / self.sample_stride;
+        let skip = index % self.sample_stride;
+        let offset = self.samples[sample];
+
+        // Decode the `skip + 1` codewords starting at `offset` out of
+        // a bounded working window, instead of cloning everything from
+        // `offset` to the end of the stream: a window sized for a
+        // handful of codewords is almost always far smaller than the
+        // untouched remainder, which is the whole point of sampling.
+        // Double the window and retry on the rare case it undershoots.
+        let mut window = (skip + 1) * INITIAL_BITS_PER_CODEWORD;
+
+        loop {
+            let end = (offset + window).min(self.bits.len());
+            let mut reader: VecDeque<bool> =
+                self.bits.iter().skip(offset).take(end - offset).cloned().collect();
+
+            let mut value = 0;
+            let mut decoded_all = true;
+            for _ in 0..=skip {
+                match Code::decode(&mut reader) {
+                    Ok(Some(v)) => value = v,
+                    _ => { decoded_all = false; break; }
+                }
+            }
+
+            if decoded_all {
+                return value - 1;
+            }
+
+            assert!(end < self.bits.len(), "stream ended before reaching `index`");
+            window *= 2;
+        }
+    }
+
+    /// Returns an iterator over all elements, decoding the stream once
+    /// from the start.
+    pub fn iter(&self) -> CompressedVecIter<Code> {
+        CompressedVecIter {
+            reader: self.bits.clone(),
+            remaining: self.len,
+            _code: PhantomData,
+        }
+    }
+
+    /// Returns the sum of the first `index` elements.
+    pub fn prefix_sum(&self, index: usize) -> u64 {
+        self.iter().take(index).sum()
+    }
+}
+
+/// Iterator over the decoded elements of a `CompressedVec`.
+pub struct CompressedVecIter<Code: UniversalCode> {
+    reader: VecDeque<bool>,
+    remaining: usize,
+    _code: PhantomData<Code>,
+}
+
+impl<Code: UniversalCode> Iterator for CompressedVecIter<Code> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        Code::decode(&mut self.reader)
+            .expect("decoding a stream written by `new`")
+            .map(|value| value - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use coding::Delta;
+
+    #[test]
+    #[should_panic]
+    fn new_panics_on_max_value() {
+        CompressedVec::<Delta>::new(&[0, u64::max_value()], 16);
+    }
+
+    #[test]
+    fn get_matches_input() {
+        let values: Vec<u64> = (0..500).map(|i| (i * 37) % 101).collect();
+        let compressed = CompressedVec::<Delta>::new(&values, 16);
+
+        assert_eq!(compressed.len(), values.len());
+        for (i, &expected) in values.iter().enumerate() {
+            assert_eq!(compressed.get(i), expected);
+        }
+    }
+
+    #[test]
+    fn iter_matches_input() {
+        let values: Vec<u64> = vec![0, 1, 2, 100, 0, 5, 9];
+        let compressed = CompressedVec::<Delta>::new(&values, 3);
+
+        let decoded: Vec<u64> = compressed.iter().collect();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn prefix_sum_matches_input() {
+        let values: Vec<u64> = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        let compressed = CompressedVec::<Delta>::new(&values, 4);
+
+        let mut running = 0;
+        for i in 0..values.len() {
+            assert_eq!(compressed.prefix_sum(i), running);
+            running += values[i];
+        }
+    }
+}
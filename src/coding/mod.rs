@@ -1,8 +1,8 @@
 //! Codes for data compression.
 //!
-//! These universal codes currently know how to encode to a `BitWrite`
-//! and decode from a `BitRead`. However, the code that would use them
-//! to implement compressed vectors and such isn’t written yet.
+//! These universal codes know how to encode to a `BitWrite` and decode
+//! from a `BitRead`. `CompressedVec` builds an indexed container of
+//! `u64`s on top of them.
 
 mod traits;
 pub use self::traits::*;
@@ -19,6 +19,11 @@ pub use self::fib::*;
 mod trans;
 pub use self::trans::*;
 
+pub mod streamvbyte;
+
+mod compvec;
+pub use self::compvec::*;
+
 #[cfg(test)]
 mod properties {
     use std::collections::VecDeque;
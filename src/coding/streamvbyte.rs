@@ -0,0 +1,123 @@
+//! Stream VByte: a byte-aligned alternative to the bit-granular
+//! universal codes, for when decode speed on large arrays matters more
+//! than getting within a bit of the entropy bound.
+//!
+//! Unlike `unary`/`elias`/`fib`, this codec doesn't implement
+//! `UniversalCode` — it isn't bit-granular, and it encodes/decodes in
+//! blocks of up to four values rather than one value at a time.
+//!
+//! Values are split into blocks of up to four `u32`s. Each block gets
+//! one control byte holding four 2-bit length codes (the byte count of
+//! each value, minus one) in a separate control stream, while the
+//! value bytes themselves go into a separate data stream with no
+//! padding or bit-shifting between values. Decoding a block reads the
+//! control byte once and then copies the indicated number of bytes per
+//! value.
+
+/// Number of bytes needed to hold `value`, at least 1.
+fn byte_len(value: u32) -> usize {
+    if value < (1 << 8) {
+        1
+    } else if value < (1 << 16) {
+        2
+    } else if value < (1 << 24) {
+        3
+    } else {
+        4
+    }
+}
+
+/// Encodes up to four values as one Stream VByte block, appending the
+/// control byte to `control` and the value bytes to `data`.
+pub fn encode_block(values: &[u32], control: &mut Vec<u8>, data: &mut Vec<u8>) {
+    assert!(!values.is_empty() && values.len() <= 4);
+
+    let mut control_byte = 0u8;
+    for (i, &value) in values.iter().enumerate() {
+        let len = byte_len(value);
+        control_byte |= ((len - 1) as u8) << (i * 2);
+
+        for b in 0..len {
+            data.push((value >> (8 * b)) as u8);
+        }
+    }
+
+    control.push(control_byte);
+}
+
+/// Decodes `count` (at most 4) values from one Stream VByte block
+/// given its control byte and a data stream starting at the block's
+/// first value byte. Returns the number of data bytes consumed.
+pub fn decode_block(control: u8, data: &[u8], count: usize, values: &mut Vec<u32>) -> usize {
+    assert!(count <= 4);
+
+    let mut offset = 0;
+    for i in 0..count {
+        let len = (((control >> (i * 2)) & 0b11) + 1) as usize;
+
+        let mut value = 0u32;
+        for b in 0..len {
+            value |= (data[offset + b] as u32) << (8 * b);
+        }
+
+        values.push(value);
+        offset += len;
+    }
+
+    offset
+}
+
+/// Encodes `values` into a (control stream, data stream) pair.
+pub fn encode(values: &[u32]) -> (Vec<u8>, Vec<u8>) {
+    let mut control = Vec::with_capacity((values.len() + 3) / 4);
+    let mut data = Vec::new();
+
+    for chunk in values.chunks(4) {
+        encode_block(chunk, &mut control, &mut data);
+    }
+
+    (control, data)
+}
+
+/// Decodes `len` values from a (control stream, data stream) pair
+/// produced by `encode`.
+pub fn decode(control: &[u8], data: &[u8], len: usize) -> Vec<u32> {
+    let mut values = Vec::with_capacity(len);
+    let mut remaining = len;
+    let mut offset = 0;
+
+    for &byte in control {
+        let count = if remaining < 4 { remaining } else { 4 };
+        offset += decode_block(byte, &data[offset..], count, &mut values);
+        remaining -= count;
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips_mixed_magnitudes() {
+        let values: Vec<u32> = vec![0, 1, 255, 256, 65535, 65536, 16777215, 16777216, u32::max_value(), 42];
+        let (control, data) = encode(&values);
+        assert_eq!(decode(&control, &data, values.len()), values);
+    }
+
+    #[test]
+    fn round_trips_non_multiple_of_four_length() {
+        let values: Vec<u32> = (0..13).map(|i| i * i * 37).collect();
+        let (control, data) = encode(&values);
+        assert_eq!(decode(&control, &data, values.len()), values);
+    }
+
+    #[test]
+    fn uses_one_byte_per_small_value() {
+        let values: Vec<u32> = vec![1, 2, 3, 4];
+        let (control, data) = encode(&values);
+        assert_eq!(control, vec![0b0000_0000]);
+        assert_eq!(data.len(), 4);
+    }
+}
@@ -0,0 +1,55 @@
+//! Bijections between integer representations, for use alongside the
+//! universal codes and byte-aligned codecs in this module.
+
+/// Maps signed integers to unsigned integers while keeping small
+/// magnitudes small (`0, -1, 1, -2, 2, ...` maps to `0, 1, 2, 3, 4,
+/// ...`), so codes built for small unsigned values — like runs of
+/// small deltas — stay cheap for signed ones too.
+pub trait ZigZag {
+    /// The unsigned counterpart of this bijection.
+    type Unsigned;
+
+    /// Maps `self` to its zig-zag encoded unsigned value.
+    fn zigzag_encode(self) -> Self::Unsigned;
+
+    /// Recovers the signed value from its zig-zag encoding.
+    fn zigzag_decode(encoded: Self::Unsigned) -> Self;
+}
+
+impl ZigZag for i64 {
+    type Unsigned = u64;
+
+    fn zigzag_encode(self) -> u64 {
+        ((self << 1) ^ (self >> 63)) as u64
+    }
+
+    fn zigzag_decode(encoded: u64) -> i64 {
+        ((encoded >> 1) as i64) ^ -((encoded & 1) as i64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips_small_and_extreme_values() {
+        let values = [
+            0i64, 1, -1, 2, -2, 1000, -1000, 12345, -54321,
+            i64::max_value(), i64::min_value(),
+        ];
+
+        for &value in &values {
+            assert_eq!(i64::zigzag_decode(value.zigzag_encode()), value);
+        }
+    }
+
+    #[test]
+    fn keeps_small_magnitudes_small() {
+        assert_eq!(0i64.zigzag_encode(), 0);
+        assert_eq!((-1i64).zigzag_encode(), 1);
+        assert_eq!(1i64.zigzag_encode(), 2);
+        assert_eq!((-2i64).zigzag_encode(), 3);
+        assert_eq!(2i64.zigzag_encode(), 4);
+    }
+}
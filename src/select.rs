@@ -1,18 +1,30 @@
 //! Data structure to support fast select queries.
 
+use std::mem;
+
+use broadword::{self, BlockType};
 use rank::Rank;
 use storage::BitStore;
 
+/// Number of one bits between consecutive entries of `HintedSelect`'s
+/// sampling array. Larger values shrink the sample at the cost of a
+/// longer linear scan per query.
+const SELECT_ONES_PER_HINT: u64 = 8192;
+
 /// Interface for types that support select queries.
 pub trait Select : BitStore {
     /// Returns the position of the `index`th 1 bit.
     fn select(&self, index: u64) -> Option<u64>;
+
+    /// Returns the position of the `index`th 0 bit.
+    fn select0(&self, index: u64) -> Option<u64>;
 }
 
 /// Performs a select query by binary searching rank queries.
 pub struct BinSearchSelect<'a, R: Rank + 'a> {
     rank_support: &'a R,
     max_rank: u64,
+    max_rank0: u64,
 }
 
 /// Creates a new binary search select support based on a rank support.
@@ -22,9 +34,11 @@ impl<'a, R: Rank + 'a> BinSearchSelect<'a, R> {
     pub fn new(rank_support: &'a R) -> Self {
         let max_index = rank_support.bit_len() - 1;
         let max_rank = rank_support.rank(max_index);
+        let max_rank0 = rank_support.bit_len() - max_rank;
         BinSearchSelect {
             rank_support: rank_support,
             max_rank: max_rank,
+            max_rank0: max_rank0,
         }
     }
 }
@@ -88,6 +102,221 @@ impl<'a, R: Rank + 'a> Select for BinSearchSelect<'a, R> {
 
         panic!("BinSearchSelect: broken invariant in rank support?");
     }
+
+    fn select0(&self, index: u64) -> Option<u64> {
+        // Same binary search as `select`, but on the number of zero
+        // bits seen so far (`mid - rank(mid) + 1`, since zeros-rank is
+        // derivable from position and ones-rank) rather than on rank.
+        let target = index + 1;
+
+        if target > self.max_rank0 { return None; }
+
+        let mut start = 0;
+        let mut limit = self.bit_len();
+
+        while start < limit {
+            let mid = start/2 + limit/2 + (start % 2 + limit % 2)/2;
+            debug_assert!(start <= mid && mid < limit);
+
+            let mid_rank0 = (mid + 1) - self.rank(mid);
+            let pre_mid_rank0 = if mid == 0 {0} else {mid - self.rank(mid - 1)};
+
+            if mid_rank0 == target && pre_mid_rank0 == target - 1 {
+                return Some(mid)
+            } else if pre_mid_rank0 > target {
+                limit = mid - 1;
+            } else if pre_mid_rank0 == target {
+                limit = mid;
+            } else if mid_rank0 < target {
+                start = mid + 1;
+            }
+        }
+
+        panic!("BinSearchSelect: broken invariant in rank support?");
+    }
+}
+
+/// Performs select queries in near-constant time by sampling, on top
+/// of a rank support's block counts, the block containing every
+/// `SELECT_ONES_PER_HINT`th one bit.
+///
+/// This is Vigna's broadword rank/select scheme: a `select(i)` query
+/// looks up the sampled block nearest to `i`, walks forward over the
+/// (monotone) block rank counts until it finds the block spanning the
+/// target bit, then locates the exact bit with a word-level popcount
+/// scan finished by [`broadword::select_in_word`].
+pub struct HintedSelect<'a, R: Rank + 'a> where R::Block: BlockType {
+    rank_support: &'a R,
+    max_rank: u64,
+    max_rank0: u64,
+    /// `sample[k]` is the index of the block containing the
+    /// `(k * SELECT_ONES_PER_HINT)`th one bit.
+    sample: Vec<usize>,
+    /// Like `sample`, but for zero bits.
+    sample0: Vec<usize>,
+}
+
+impl<'a, R: Rank + 'a> HintedSelect<'a, R> where R::Block: BlockType {
+    /// Creates a new hinted selection support given a rank support,
+    /// building the one and zero sampling arrays with a single linear
+    /// scan over the underlying blocks.
+    pub fn new(rank_support: &'a R) -> Self {
+        let max_index = rank_support.bit_len() - 1;
+        let max_rank = rank_support.rank(max_index);
+        let max_rank0 = rank_support.bit_len() - max_rank;
+        let block_bit_len = (mem::size_of::<R::Block>() * 8) as u64;
+
+        let mut sample = vec![0];
+        let mut sample0 = vec![0];
+        let mut next_sampled_rank = SELECT_ONES_PER_HINT;
+        let mut next_sampled_rank0 = SELECT_ONES_PER_HINT;
+        let mut rank_before_block = 0u64;
+        let mut rank0_before_block = 0u64;
+
+        for block in 0..rank_support.block_len() {
+            let ones_in_block = rank_support.get_block(block).count_ones() as u64;
+            let zeros_in_block = block_bit_len - ones_in_block;
+            let rank_after_block = rank_before_block + ones_in_block;
+            let rank0_after_block = rank0_before_block + zeros_in_block;
+
+            while next_sampled_rank < rank_after_block {
+                sample.push(block);
+                next_sampled_rank += SELECT_ONES_PER_HINT;
+            }
+
+            while next_sampled_rank0 < rank0_after_block {
+                sample0.push(block);
+                next_sampled_rank0 += SELECT_ONES_PER_HINT;
+            }
+
+            rank_before_block = rank_after_block;
+            rank0_before_block = rank0_after_block;
+        }
+
+        HintedSelect {
+            rank_support: rank_support,
+            max_rank: max_rank,
+            max_rank0: max_rank0,
+            sample: sample,
+            sample0: sample0,
+        }
+    }
+}
+
+impl<'a, R: Rank + 'a> BitStore for HintedSelect<'a, R> where R::Block: BlockType {
+    type Block = R::Block;
+
+    fn block_len(&self) -> usize {
+        self.rank_support.block_len()
+    }
+
+    fn bit_len(&self) -> u64 {
+        self.rank_support.bit_len()
+    }
+
+    fn get_block(&self, index: usize) -> Self::Block {
+        self.rank_support.get_block(index)
+    }
+
+    fn get_bit(&self, index: u64) -> bool {
+        self.rank_support.get_bit(index)
+    }
+}
+
+impl<'a, R: Rank + 'a> Rank for HintedSelect<'a, R> where R::Block: BlockType {
+    fn rank(&self, index: u64) -> u64 {
+        self.rank_support.rank(index)
+    }
+}
+
+impl<'a, R: Rank + 'a> Select for HintedSelect<'a, R> where R::Block: BlockType {
+    fn select(&self, index: u64) -> Option<u64> {
+        let rank = index + 1;
+        if rank > self.max_rank {
+            return None;
+        }
+
+        let block_bit_len = (mem::size_of::<R::Block>() * 8) as u64;
+
+        // Jump to the nearest sampled block, then walk forward over
+        // the (monotone) block rank counts until we overshoot the
+        // target: that block contains the bit we want.
+        let hint = (index / SELECT_ONES_PER_HINT) as usize;
+        let mut block = self.sample[hint];
+        let mut rank_before_block = if block == 0 {
+            0
+        } else {
+            self.rank_support.rank(block as u64 * block_bit_len - 1)
+        };
+
+        loop {
+            let ones_in_block = self.rank_support.get_block(block).count_ones() as u64;
+            let rank_after_block = rank_before_block + ones_in_block;
+
+            if rank_after_block >= rank {
+                break;
+            }
+
+            rank_before_block = rank_after_block;
+            block += 1;
+        }
+
+        // The target bit is inside this block's word; find it with a
+        // single broadword in-word select. Bits are numbered MSB-first
+        // within a block, but `select_in_word` counts from the LSB, so
+        // reverse the block's bits (within its true width, since
+        // `R::Block` can be narrower than 64 bits) before searching.
+        let remaining = (rank - rank_before_block - 1) as u32;
+        let word = self.rank_support.get_block(block).to_u64();
+        let reversed = word.reverse_bits() >> (64 - block_bit_len);
+        let bit = broadword::select_in_word(reversed, remaining);
+
+        Some(block as u64 * block_bit_len + bit as u64)
+    }
+
+    fn select0(&self, index: u64) -> Option<u64> {
+        let rank0 = index + 1;
+        if rank0 > self.max_rank0 {
+            return None;
+        }
+
+        let block_bit_len = (mem::size_of::<R::Block>() * 8) as u64;
+
+        // Same idea as `select`, but sampled and scanned over the
+        // zero-bit counts instead of the one-bit counts.
+        let hint = (index / SELECT_ONES_PER_HINT) as usize;
+        let mut block = self.sample0[hint];
+        let mut rank0_before_block = if block == 0 {
+            0
+        } else {
+            let bit_index = block as u64 * block_bit_len - 1;
+            bit_index + 1 - self.rank_support.rank(bit_index)
+        };
+
+        loop {
+            let ones_in_block = self.rank_support.get_block(block).count_ones() as u64;
+            let zeros_in_block = block_bit_len - ones_in_block;
+            let rank0_after_block = rank0_before_block + zeros_in_block;
+
+            if rank0_after_block >= rank0 {
+                break;
+            }
+
+            rank0_before_block = rank0_after_block;
+            block += 1;
+        }
+
+        // Select within the complemented, bit-reversed word so the
+        // `k`th zero bit (MSB-first) becomes the `k`th one bit found by
+        // an LSB-first in-word select. Complementing commutes with the
+        // reversal, so it's applied after, same as in `select`.
+        let remaining = (rank0 - rank0_before_block - 1) as u32;
+        let word = self.rank_support.get_block(block).to_u64();
+        let reversed = word.reverse_bits() >> (64 - block_bit_len);
+        let bit = broadword::select_in_word(!reversed, remaining);
+
+        Some(block as u64 * block_bit_len + bit as u64)
+    }
 }
 
 #[cfg(test)]
@@ -141,6 +370,68 @@ mod test {
         assert_eq!(Some(919), select.select(459));
     }
 
+    #[test]
+    fn select0_1() {
+        let vec = vec![ 0b01010101010101010101010101010101u32; 1024 ];
+        let rank = JacobsonRank::new(&*vec);
+        let select = BinSearchSelect::new(&rank);
+
+        assert_eq!(Some(0), select.select0(0));
+        assert_eq!(Some(2), select.select0(1));
+        assert_eq!(Some(4), select.select0(2));
+        assert_eq!(Some(6), select.select0(3));
+        assert_eq!(Some(918), select.select0(459));
+        assert_eq!(None, select.select0(16384));
+    }
+
+    #[test]
+    fn hinted_select0_matches_bin_search_select0() {
+        let vec = vec![ 0b10000000000000001110000000000000u32; 1024 ];
+        let rank = JacobsonRank::new(&*vec);
+        let bin_search = BinSearchSelect::new(&rank);
+        let hinted = HintedSelect::new(&rank);
+
+        // 1024 words * 28 zero bits each: the actual zero count.
+        for i in 0..(1024 * 28) {
+            assert_eq!(bin_search.select0(i), hinted.select0(i));
+        }
+        assert_eq!(None, hinted.select0(1024 * 28));
+    }
+
+    #[test]
+    fn hinted_select_matches_bin_search_select() {
+        // 8192 words * 4 one bits each is 32768 ones, several times
+        // `SELECT_ONES_PER_HINT` (8192), so the sampling
+        // array grows past its initial entry and the forward scan
+        // from a non-zero hint is actually exercised.
+        let vec = vec![ 0b10000000000000001110000000000000u32; 8192 ];
+        let rank = JacobsonRank::new(&*vec);
+        let bin_search = BinSearchSelect::new(&rank);
+        let hinted = HintedSelect::new(&rank);
+
+        for i in 0..(8192 * 4) {
+            assert_eq!(bin_search.select(i), hinted.select(i));
+        }
+        assert_eq!(None, hinted.select(8192 * 4));
+    }
+
+    #[test]
+    fn hinted_select_single_top_bit() {
+        // A single 64-bit word with only bit 0 (the word's top bit, not
+        // its LSB) set. `broadword::select_in_word` counts from the LSB,
+        // so a hinted select that forgets to reverse the word before
+        // searching would return 63 here instead of 0.
+        let vec = vec![ 0x8000_0000_0000_0000u64 ];
+        let rank = JacobsonRank::new(&*vec);
+        let bin_search = BinSearchSelect::new(&rank);
+        let hinted = HintedSelect::new(&rank);
+
+        assert_eq!(Some(0), bin_search.select(0));
+        assert_eq!(Some(0), hinted.select(0));
+        assert_eq!(Some(1), bin_search.select0(0));
+        assert_eq!(Some(1), hinted.select0(0));
+    }
+
     #[test]
     fn select3() {
         let vec = vec![ 0b11111111111111111111111111111111u32; 1024 ];